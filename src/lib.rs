@@ -12,6 +12,7 @@ use std::fmt;
 use std::hash;
 use std::isize;
 use std::marker::PhantomData;
+use std::mem::ManuallyDrop;
 use std::ops::Deref;
 use std::panic;
 use std::process::abort;
@@ -25,57 +26,177 @@ use std::sync::atomic::{self, AtomicUsize, Ordering};
 const MAX_REFCOUNT: usize = (isize::MAX) as usize;
 
 enum Count {
-    Single(Cell<usize>),
-    Multi(AtomicUsize),
+    Single {
+        strong: Cell<usize>,
+        weak: Cell<usize>,
+    },
+    Multi {
+        strong: AtomicUsize,
+        weak: AtomicUsize,
+    },
 }
 
 struct Inner<T: ?Sized> {
     count: UnsafeCell<Count>,
-    data: T,
+    // Kept alive until the last `Weak` is dropped, even after `data` itself is gone.
+    data: ManuallyDrop<T>,
 }
 
 impl<T> Inner<T> {
     fn new(data: T) -> Box<Self> {
         Box::new(Self {
-            count: Count::Single(1.into()).into(),
-            data,
+            // The set of strong pointers collectively holds one weak reference, so `weak` starts
+            // at 1 rather than 0.
+            count: Count::Single {
+                strong: 1.into(),
+                weak: 1.into(),
+            }
+            .into(),
+            data: ManuallyDrop::new(data),
         })
     }
 }
 
 impl<T: ?Sized> Inner<T> {
     unsafe fn make_multi_threaded(&self) {
-        let count = match &*self.count.get() {
-            Count::Single(cell) => cell.get(),
-            Count::Multi(_) => return,
+        let (strong, weak) = match &*self.count.get() {
+            Count::Single { strong, weak } => (strong.get(), weak.get()),
+            Count::Multi { .. } => return,
         };
         // We're single-threaded, so we can safely do an unsynchronized write.
-        *self.count.get() = Count::Multi(count.into());
+        *self.count.get() = Count::Multi {
+            strong: strong.into(),
+            weak: weak.into(),
+        };
     }
 
     unsafe fn make_single_threaded(&self) -> bool {
-        let count = match &*self.count.get() {
-            Count::Single(_) => return true,
-            Count::Multi(atom) => atom.load(Ordering::SeqCst),
+        // Separately loading `strong` and `weak` isn't enough: `Weak::upgrade` can mint a brand
+        // new strong reference (from another thread, with no live strong handle required
+        // beforehand) between the two loads, so a stale "sole owner" snapshot could pass this
+        // check while a second, very much alive, `Rc` is being driven through non-atomic `Cell`s
+        // elsewhere. Instead, atomically claim sole ownership of `strong` first: a successful
+        // `1 -> 0` transition is only possible if `strong` was truly 1 at that instant, and while
+        // it reads 0, `Weak::upgrade`'s own CAS loop can't succeed (it bails out as soon as it
+        // sees 0). That makes the allocation's only live strong handle immovably ours. A new `Weak`
+        // can only come from `downgrade`, which requires a live strong handle (and we're now
+        // holding the only one), or from cloning an existing `Weak`; so once `weak` reads 1 there
+        // is no `Weak` left to clone, and `weak` can no longer change underneath us either.
+        let transition = match &*self.count.get() {
+            Count::Single { .. } => return true,
+            Count::Multi { strong, weak } => {
+                if strong
+                    .compare_exchange(1, 0, Ordering::SeqCst, Ordering::SeqCst)
+                    .is_err()
+                {
+                    return false;
+                }
+                if weak.load(Ordering::SeqCst) == 1 {
+                    true
+                } else {
+                    strong.store(1, Ordering::SeqCst);
+                    false
+                }
+            }
         };
-        if count == 1 {
-            // We're the sole owner, so we can safely do an unsynchronized write.
-            *self.count.get() = Count::Single(count.into());
-            true
-        } else {
-            false
+        if transition {
+            // We're the sole owner, with no outstanding `Weak`, so we can safely do an
+            // unsynchronized write.
+            *self.count.get() = Count::Single {
+                strong: 1.into(),
+                weak: 1.into(),
+            };
+        }
+        transition
+    }
+
+    fn increment_strong(&self) -> usize {
+        unsafe {
+            let count = match &*self.count.get() {
+                Count::Single { strong, .. } => {
+                    let count = strong.get() + 1;
+                    strong.set(count);
+                    count
+                }
+                Count::Multi { strong, .. } => strong.fetch_add(1, Ordering::Relaxed) + 1,
+            };
+            if count > MAX_REFCOUNT {
+                abort();
+            }
+            count
+        }
+    }
+
+    fn decrement_strong(&self) -> usize {
+        unsafe {
+            match &*self.count.get() {
+                Count::Single { strong, .. } => {
+                    let count = strong.get() - 1;
+                    strong.set(count);
+                    count
+                }
+                Count::Multi { strong, .. } => {
+                    let count = strong.fetch_sub(1, Ordering::Release) - 1;
+                    if count == 0 {
+                        atomic::fence(Ordering::Acquire);
+                    }
+                    count
+                }
+            }
         }
     }
 
-    fn increment(&self) -> usize {
+    /// Attempts to create a new strong reference out of a weak one, incrementing `strong` only
+    /// if it isn't already zero.
+    fn try_increment_strong(&self) -> bool {
+        unsafe {
+            match &*self.count.get() {
+                Count::Single { strong, .. } => {
+                    let count = strong.get();
+                    if count == 0 {
+                        false
+                    } else {
+                        let count = count + 1;
+                        if count > MAX_REFCOUNT {
+                            abort();
+                        }
+                        strong.set(count);
+                        true
+                    }
+                }
+                Count::Multi { strong, .. } => {
+                    let mut count = strong.load(Ordering::Relaxed);
+                    loop {
+                        if count == 0 {
+                            return false;
+                        }
+                        if count > MAX_REFCOUNT {
+                            abort();
+                        }
+                        match strong.compare_exchange_weak(
+                            count,
+                            count + 1,
+                            Ordering::Acquire,
+                            Ordering::Relaxed,
+                        ) {
+                            Ok(_) => return true,
+                            Err(actual) => count = actual,
+                        }
+                    }
+                }
+            }
+        }
+    }
+
+    fn increment_weak(&self) -> usize {
         unsafe {
             let count = match &*self.count.get() {
-                Count::Single(cell) => {
-                    let count = cell.get() + 1;
-                    cell.set(count);
+                Count::Single { weak, .. } => {
+                    let count = weak.get() + 1;
+                    weak.set(count);
                     count
                 }
-                Count::Multi(atom) => atom.fetch_add(1, Ordering::Relaxed) + 1,
+                Count::Multi { weak, .. } => weak.fetch_add(1, Ordering::Relaxed) + 1,
             };
             if count > MAX_REFCOUNT {
                 abort();
@@ -84,16 +205,16 @@ impl<T: ?Sized> Inner<T> {
         }
     }
 
-    fn decrement(&self) -> usize {
+    fn decrement_weak(&self) -> usize {
         unsafe {
             match &*self.count.get() {
-                Count::Single(cell) => {
-                    let count = cell.get() - 1;
-                    cell.set(count);
+                Count::Single { weak, .. } => {
+                    let count = weak.get() - 1;
+                    weak.set(count);
                     count
                 }
-                Count::Multi(atom) => {
-                    let count = atom.fetch_sub(1, Ordering::Release) - 1;
+                Count::Multi { weak, .. } => {
+                    let count = weak.fetch_sub(1, Ordering::Release) - 1;
                     if count == 0 {
                         atomic::fence(Ordering::Acquire);
                     }
@@ -143,19 +264,40 @@ impl<T> Rc<T> {
     pub fn unshare(this: &Self) -> bool {
         unsafe { this.inner().make_single_threaded() }
     }
+
+    /// Creates a new `Weak<T>` pointer to this allocation.
+    ///
+    /// Since a `Weak` may end up sent to another thread, this switches the allocation to use
+    /// atomic access to its reference counts, just like converting to an `Arc` would.
+    pub fn downgrade(this: &Self) -> Weak<T> {
+        unsafe { this.inner().make_multi_threaded() };
+        this.inner().increment_weak();
+        Weak {
+            inner: this.inner,
+            phantom: PhantomData,
+        }
+    }
 }
 
 impl<T: ?Sized> Clone for Rc<T> {
     fn clone(&self) -> Self {
-        self.inner().increment();
+        self.inner().increment_strong();
         Self { ..*self }
     }
 }
 
 impl<T: ?Sized> Drop for Rc<T> {
     fn drop(&mut self) {
-        if self.inner().decrement() == 0 {
-            drop(unsafe { Box::from_raw(self.inner.as_ptr()) });
+        if self.inner().decrement_strong() == 0 {
+            unsafe {
+                ManuallyDrop::drop(&mut (*self.inner.as_ptr()).data);
+            }
+            // The set of strong pointers collectively held one weak reference; drop it now that
+            // the last strong pointer is gone, freeing the allocation if that was the last weak
+            // reference too.
+            if self.inner().decrement_weak() == 0 {
+                drop(unsafe { Box::from_raw(self.inner.as_ptr()) });
+            }
         }
     }
 }
@@ -258,6 +400,68 @@ impl<T: ?Sized + Ord> Ord for Rc<T> {
 
 impl<T: panic::RefUnwindSafe + ?Sized> panic::UnwindSafe for Rc<T> {}
 
+/// `Weak` is a version of [`Rc`] that holds a non-owning reference to the managed allocation.
+///
+/// The allocation is accessed by calling [`upgrade`] on the `Weak` pointer, which returns an
+/// `Option<Rc<T>>`. Like `Rc` and `Arc`, it doesn't matter whether the strong pointers that a
+/// `Weak` was created from (or upgrades into) are `Rc`s or `Arc`s: the atomicity of the
+/// reference counts is a property of the allocation, not of any particular handle.
+///
+/// [`upgrade`]: Weak::upgrade
+pub struct Weak<T: ?Sized> {
+    inner: NonNull<Inner<T>>,
+    phantom: PhantomData<Inner<T>>,
+}
+
+// NB: `downgrade` **must** switch the inner count to the synchronized `Count::Multi`!
+unsafe impl<T: Send + Sync + ?Sized> Send for Weak<T> {}
+unsafe impl<T: Send + Sync + ?Sized> Sync for Weak<T> {}
+
+impl<T: ?Sized> Weak<T> {
+    fn inner(&self) -> &Inner<T> {
+        unsafe { self.inner.as_ref() }
+    }
+
+    /// Attempts to upgrade this `Weak` pointer to an `Rc`, delaying the drop of the allocation's
+    /// data for as long as the returned value is alive.
+    ///
+    /// Returns `None` if the data has already been dropped, i.e. if every strong pointer to the
+    /// allocation has already been dropped.
+    pub fn upgrade(&self) -> Option<Rc<T>> {
+        if self.inner().try_increment_strong() {
+            Some(Rc {
+                inner: self.inner,
+                phantom: PhantomData,
+            })
+        } else {
+            None
+        }
+    }
+}
+
+impl<T: ?Sized> Clone for Weak<T> {
+    fn clone(&self) -> Self {
+        self.inner().increment_weak();
+        Self { ..*self }
+    }
+}
+
+impl<T: ?Sized> Drop for Weak<T> {
+    fn drop(&mut self) {
+        if self.inner().decrement_weak() == 0 {
+            drop(unsafe { Box::from_raw(self.inner.as_ptr()) });
+        }
+    }
+}
+
+impl<T: ?Sized> fmt::Debug for Weak<T> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "(Weak)")
+    }
+}
+
+impl<T: panic::RefUnwindSafe + ?Sized> panic::UnwindSafe for Weak<T> {}
+
 /// A thread-safe reference-counting pointer. 'Arc' stands for 'Atomically Reference Counted'.
 pub struct Arc<T: ?Sized> {
     inner: Rc<T>,
@@ -279,6 +483,11 @@ impl<T> Arc<T> {
         unsafe { rc.inner().make_multi_threaded() };
         Self { inner: rc }
     }
+
+    /// Creates a new `Weak<T>` pointer to this allocation.
+    pub fn downgrade(this: &Self) -> Weak<T> {
+        Rc::downgrade(&this.inner)
+    }
 }
 
 impl<T: ?Sized> Clone for Arc<T> {
@@ -386,3 +595,41 @@ impl<T: ?Sized + Ord> Ord for Arc<T> {
 }
 
 impl<T: panic::RefUnwindSafe + ?Sized> panic::UnwindSafe for Arc<T> {}
+
+#[cfg(test)]
+mod tests {
+    use super::{Arc, Rc};
+    use std::sync::{Arc as StdArc, Barrier};
+    use std::thread;
+
+    /// Regression test for a race between `Rc::unshare` and a concurrent `Weak::upgrade`: an
+    /// `unshare` that only loads `strong` and `weak` separately can observe a stale "sole owner"
+    /// snapshot while another thread's freshly-upgraded `Rc` is still alive, corrupting the count
+    /// by switching it to unsynchronized `Cell`s out from under that second strong reference.
+    #[test]
+    fn unshare_races_with_weak_upgrade() {
+        for _ in 0..1000 {
+            let arc = Arc::new(0u32);
+            let weak = Arc::downgrade(&arc);
+            let barrier = StdArc::new(Barrier::new(2));
+
+            let unshare_barrier = barrier.clone();
+            let unshare_thread = thread::spawn(move || {
+                let rc = Rc::from_arc(arc);
+                unshare_barrier.wait();
+                Rc::unshare(&rc);
+                drop(rc);
+            });
+
+            let upgrade_thread = thread::spawn(move || {
+                barrier.wait();
+                if let Some(upgraded) = weak.upgrade() {
+                    drop(upgraded);
+                }
+            });
+
+            unshare_thread.join().unwrap();
+            upgrade_thread.join().unwrap();
+        }
+    }
+}